@@ -13,7 +13,7 @@ use hal::{
 };
 use stm32f1xx_hal as hal; // STM32F1 specific functions
 
-use lcd_1602_i2c::Lcd;
+use lcd_1602_i2c::{Controller, Lcd, Pca9633Backlight};
 
 #[allow(unused_imports)]
 use panic_halt; // When a panic occurs, stop the microcontroller
@@ -51,7 +51,8 @@ fn main() -> ! {
         1000,
     );
 
-    let mut lcd = Lcd::new(i2c_bus, LCD_ADDRESS, RGB_ADDRESS, &mut delay).unwrap();
+    let backlight = Pca9633Backlight::new(RGB_ADDRESS);
+    let mut lcd = Lcd::new(i2c_bus, LCD_ADDRESS, backlight, Controller::Aip31068, 2, 16, &mut delay).unwrap();
     lcd.set_rgb(255, 255, 255).unwrap();
     lcd.print("Hello world!").unwrap();
 
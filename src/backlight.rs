@@ -0,0 +1,104 @@
+#![deny(missing_docs)]
+
+use embedded_hal::blocking::i2c;
+
+/**
+Abstracts over the different ways a board can drive its backlight, from an RGB
+PCA9633 LED driver down to a simple on/off bit, or no backlight at all. `Lcd<I, B>`
+is generic over this trait so boards without an RGB chip aren't forced to pay for
+the PCA9633 register writes that run during `init()`.
+*/
+pub trait Backlight<I>
+where
+    I: i2c::Write,
+{
+    /// Performs any one-time setup the backlight hardware needs. Called once
+    /// from `Lcd::new()`.
+    fn init(&mut self, i2c: &mut I) -> Result<(), I::Error>;
+
+    /// Sets the backlight color. Implementations that don't support RGB may
+    /// ignore the individual channels and just turn the backlight on if any of
+    /// them are non-zero.
+    fn set_color(&mut self, i2c: &mut I, r: u8, g: u8, b: u8) -> Result<(), I::Error>;
+
+    /// Turns the backlight fully on or off.
+    fn set_on(&mut self, i2c: &mut I, on: bool) -> Result<(), I::Error>;
+}
+
+/**
+Drives an RGB backlight through a PCA9633 LED driver chip, as found on boards
+like the Waveshare LCD1602 RGB module.
+*/
+pub struct Pca9633Backlight {
+    address: u8,
+}
+
+impl Pca9633Backlight {
+    /// Creates a new backlight driver for the PCA9633 chip at `address`.
+    pub fn new(address: u8) -> Self {
+        Pca9633Backlight { address }
+    }
+
+    fn set_reg<I>(&self, i2c: &mut I, addr: u8, data: u8) -> Result<(), I::Error>
+    where
+        I: i2c::Write,
+    {
+        i2c.write(self.address, &[addr, data])
+    }
+}
+
+impl<I> Backlight<I> for Pca9633Backlight
+where
+    I: i2c::Write,
+{
+    fn init(&mut self, i2c: &mut I) -> Result<(), I::Error> {
+        const REG_MODE1: u8 = 0x00;
+        const REG_MODE2: u8 = 0x01;
+        const REG_OUTPUT: u8 = 0x08;
+
+        self.set_reg(i2c, REG_MODE1, 0)?;
+
+        // Set the LEDs controllable by both PWM and GRPPWM registers
+        self.set_reg(i2c, REG_OUTPUT, 0xFF)?;
+        self.set_reg(i2c, REG_MODE2, 0x20)
+    }
+
+    fn set_color(&mut self, i2c: &mut I, r: u8, g: u8, b: u8) -> Result<(), I::Error> {
+        const REG_RED: u8 = 0x04; // pwm2
+        const REG_GREEN: u8 = 0x03; // pwm1
+        const REG_BLUE: u8 = 0x02; // pwm0
+
+        self.set_reg(i2c, REG_RED, r)?;
+        self.set_reg(i2c, REG_GREEN, g)?;
+        self.set_reg(i2c, REG_BLUE, b)
+    }
+
+    fn set_on(&mut self, i2c: &mut I, on: bool) -> Result<(), I::Error> {
+        let level = if on { 0xFF } else { 0 };
+        self.set_color(i2c, level, level, level)
+    }
+}
+
+/**
+A no-op backlight for boards that have no backlight control, or whose backlight
+is wired independently of the I2C bus. `init()`, `set_color()` and `set_on()` all
+do nothing.
+*/
+pub struct NoBacklight;
+
+impl<I> Backlight<I> for NoBacklight
+where
+    I: i2c::Write,
+{
+    fn init(&mut self, _i2c: &mut I) -> Result<(), I::Error> {
+        Ok(())
+    }
+
+    fn set_color(&mut self, _i2c: &mut I, _r: u8, _g: u8, _b: u8) -> Result<(), I::Error> {
+        Ok(())
+    }
+
+    fn set_on(&mut self, _i2c: &mut I, _on: bool) -> Result<(), I::Error> {
+        Ok(())
+    }
+}
@@ -47,6 +47,42 @@ impl DisplayControl {
     }
 }
 
+/// Controls which direction the cursor moves, and the display shifts, as characters are written.
+#[derive(Copy, Clone)]
+pub enum TextDirection {
+    /// The cursor increments and text is entered left to right
+    LeftToRight = 2,
+    /// The cursor decrements and text is entered right to left
+    RightToLeft = 0,
+}
+
+/// Controls whether the display shifts as each character is written, giving the
+/// appearance of the cursor staying still while the text scrolls past it.
+#[derive(Copy, Clone)]
+pub enum Autoscroll {
+    /// Shift the display with each character written
+    On = 1,
+    /// Leave the display static as characters are written
+    Off = 0,
+}
+
+pub struct EntryMode {
+    pub direction: TextDirection,
+    pub autoscroll: Autoscroll,
+}
+
+impl EntryMode {
+    pub fn new() -> Self {
+        EntryMode {
+            direction: TextDirection::LeftToRight,
+            autoscroll: Autoscroll::Off,
+        }
+    }
+    pub fn value(&self) -> u8 {
+        0x04 | self.direction as u8 | self.autoscroll as u8
+    }
+}
+
 #[cfg(test)]
 mod test2 {
     use super::*;
@@ -86,3 +122,28 @@ mod test2 {
         assert_eq!(0x08 | 4 | 2, control.value());
     }
 }
+
+#[cfg(test)]
+mod test3 {
+    use super::*;
+
+    #[test]
+    fn defaults_to_left_to_right_without_autoscroll() {
+        let entry_mode = EntryMode::new();
+        assert_eq!(0x04 | 2, entry_mode.value());
+    }
+
+    #[test]
+    fn right_to_left() {
+        let mut entry_mode = EntryMode::new();
+        entry_mode.direction = TextDirection::RightToLeft;
+        assert_eq!(0x04, entry_mode.value());
+    }
+
+    #[test]
+    fn autoscroll_on() {
+        let mut entry_mode = EntryMode::new();
+        entry_mode.autoscroll = Autoscroll::On;
+        assert_eq!(0x04 | 2 | 1, entry_mode.value());
+    }
+}
@@ -1,77 +1,137 @@
 /*!
-# Platform-agnostic driver for I2C 16x2 character displays
+# Platform-agnostic driver for I2C character displays
 
-Provides a driver for common 16x2 LCD displays that use the AiP31068L chip to
-drive the display, and a PCA9633 chip to drive the RGB backlight.
-
-This is a basic implementation, and doesn't currently support custom characters.
+Provides a driver for HD44780-compatible character displays that use the
+AiP31068L chip to drive the display. `rows` and `cols` are passed to `Lcd::new()`
+so the driver isn't tied to any one geometry: 16x2 is the most common, but 16x1,
+20x4 and other HD44780-compatible layouts are supported too. The backlight is
+driven through the `Backlight` trait, so boards with a PCA9633 RGB backlight, a
+simple on/off backlight bit, or no backlight at all are all supported.
 
 This has been tested with the [Waveshare LCD1602 module](https://www.waveshare.com/wiki/LCD1602_RGB_Module).
 It may also work with other RGB displays like the [Groove 16X2 LDC RGB](https://www.seeedstudio.com/Grove-LCD-RGB-Backlight-p-1643.html)
 */
 
 #![no_std]
+use core::fmt;
 use embedded_hal::blocking::{i2c, delay::DelayMs};
 
+mod backlight;
 mod display_control;
-use display_control::{DisplayControl};
+use display_control::{DisplayControl, EntryMode};
+
+pub use backlight::{Backlight, NoBacklight, Pca9633Backlight};
+pub use display_control::{Autoscroll, Blink, Cursor, LcdDisplay, TextDirection};
 
-pub use display_control::{Cursor, LcdDisplay, Blink};
+/**
+Identifies the character controller chip driving the display. Most boards use a
+plain AiP31068L/HD44780-compatible controller, but some pair an ST7032i-family
+controller that adds an extended instruction table for contrast control.
+*/
+#[derive(Copy, Clone, PartialEq)]
+pub enum Controller {
+    /// AiP31068L/HD44780-compatible controller with no extended instruction set
+    Aip31068,
+    /// ST7032i-family controller with an extended instruction set for contrast control
+    St7032i,
+}
 
 /**
 Handles all the logic related to working with the character LCD via I2C. You'll
 need to create an instance of this with the `new()` method.
 
 The `I` generic type needs to implement the `embedded_hal::blocking::Write` trait.
+The `B` generic type needs to implement the `Backlight<I>` trait, and picks which
+backlight hardware (if any) the board uses.
 */
-pub struct Lcd<I>
+pub struct Lcd<I, B>
 where
     I: i2c::Write,
+    B: Backlight<I>,
 {
     i2c: I,
     show_function: u8,
     control: DisplayControl,
+    entry_mode: EntryMode,
     address: u8,
-    rgb_address: u8,
+    backlight: B,
+    controller: Controller,
+    rows: u8,
+    cols: u8,
+    row_offsets: [u8; 4],
+    cursor_col: u8,
+    cursor_row: u8,
+    last_error: Option<<I as i2c::Write>::Error>,
 }
 
-impl<I> Lcd<I>
+impl<I, B> Lcd<I, B>
 where
-    I: i2c::Write
+    I: i2c::Write,
+    B: Backlight<I>,
     {
     /**
     Creates a new instance of the display object.
 
+    `backlight` drives whatever backlight hardware the board has, e.g.
+    `Pca9633Backlight::new(rgb_address)` for an RGB backlight, or `NoBacklight`
+    for boards without one.
+
+    `controller` identifies the character controller chip, since only ST7032i-family
+    controllers support `set_contrast()`.
+
+    `rows` and `cols` describe the display's geometry (e.g. `2, 16` for the common
+    16x2 module, or `4, 20` for a 20x4 module) and are used to compute the DDRAM
+    address for each row, as well as the Function Set line-count bit.
+
     # Example
 
     ```rust
-    let lcd = Lcd::new(i2c_bus, address, rgb_address, &mut delay);
+    let lcd = Lcd::new(i2c_bus, address, Pca9633Backlight::new(rgb_address), Controller::Aip31068, 2, 16, &mut delay);
     ```
 
     `i2c` needs to implement the `embedded_hal::blocking::Write` trait.
 
     `delay` needs to implement the `embedded_hal::blocking::delay::DelayMs` trait.
 
+    # Panics
+
+    Panics if `rows` is not between 1 and 4, or if `cols` is 0 or greater than
+    191 (the row-offset table can't represent a third/fourth row beyond that).
+
     # Errors
 
     The I2C library will return an error if it's not able to write to the device.
     This is always a trait of type `embedded_hal::blocking::Write::Error` that
     is implemented by the I2C instance.
     */
-    pub fn new<D>(i2c: I, address: u8, rgb_address: u8, delay: &mut D) -> Result<Self, <I as i2c::Write>::Error>
+    pub fn new<D>(i2c: I, address: u8, backlight: B, controller: Controller, rows: u8, cols: u8, delay: &mut D) -> Result<Self, <I as i2c::Write>::Error>
     where
         D: DelayMs<u16>
     {
+        assert!((1..=4).contains(&rows), "rows must be between 1 and 4");
+        assert!(cols > 0 && cols <= 0xBF, "cols must be between 1 and 191");
+
         const LCD_4BITMODE: u8 = 0x00;
+        const LCD_1LINE: u8 = 0x00;
         const LCD_2LINE: u8 = 0x08;
         const LCD_5X8_DOTS: u8 = 0x00;
 
+        let line_mode = if rows > 1 { LCD_2LINE } else { LCD_1LINE };
+
         let mut display = Lcd {
             i2c,
-            show_function: LCD_4BITMODE | LCD_2LINE | LCD_5X8_DOTS,
+            show_function: LCD_4BITMODE | line_mode | LCD_5X8_DOTS,
             control: DisplayControl::new(),
+            entry_mode: EntryMode::new(),
             address,
-            rgb_address,
+            backlight,
+            controller,
+            rows,
+            cols,
+            row_offsets: [0x00, 0x40, cols, 0x40 + cols],
+            cursor_col: 0,
+            cursor_row: 0,
+            last_error: None,
         };
         display.init(delay)?;
         Ok(display)
@@ -98,22 +158,10 @@ where
         self.clear(delay)?;
 
         // Display entry mode
-        const LCD_ENTRYLEFT: u8 = 0x02;
-        const LCD_ENTRYSHIFTDECREMENT: u8 = 0x00;
-        const LCD_ENTRYMODESET: u8 = 0x04;
-
-        self.command(LCD_ENTRYMODESET | LCD_ENTRYLEFT | LCD_ENTRYSHIFTDECREMENT)?;
+        self.update_entry_mode()?;
 
         // Initialize the backlight
-        const REG_MODE1: u8     = 0x00;
-        const REG_MODE2: u8     = 0x01;
-        const REG_OUTPUT: u8    = 0x08;
-    
-        self.set_reg(REG_MODE1, 0)?;
-
-        // Set the LEDs controllable by both PWM and GRPPWM registers
-        self.set_reg(REG_OUTPUT, 0xFF)?;
-        self.set_reg(REG_MODE2, 0x20)
+        self.backlight.init(&mut self.i2c)
     }
 
     /**
@@ -133,15 +181,73 @@ where
     }
 
     /**
-    Set the position of the cursor
+    Moves the cursor and the display back to the top-left position, undoing any
+    display shift caused by `scroll_display_left()`/`scroll_display_right()` or
+    autoscrolling. Requires a ~2ms delay after sending, which is why this method
+    requires a `delay` object.
+
+    # Errors
+
+    Returns a `Result` that will report I2C errors, if any.
+    */
+    pub fn return_home(&mut self, delay: &mut dyn DelayMs<u16>) -> Result<(), <I as i2c::Write>::Error> {
+        const LCD_RETURNHOME: u8 = 0x02;
+
+        let result = self.command(LCD_RETURNHOME);
+        delay.delay_ms(2);
+        result
+    }
+
+    /**
+    Shifts the whole display one position to the left, without changing the
+    contents of DDRAM. Useful for marquee-style scrolling.
+
+    # Errors
+
+    Returns a `Result` that will report I2C errors, if any.
+    */
+    pub fn scroll_display_left(&mut self) -> Result<(), <I as i2c::Write>::Error> {
+        const LCD_CURSORSHIFT: u8 = 0x10;
+        const LCD_DISPLAYMOVE: u8 = 0x08;
+
+        self.command(LCD_CURSORSHIFT | LCD_DISPLAYMOVE)
+    }
+
+    /**
+    Shifts the whole display one position to the right, without changing the
+    contents of DDRAM. Useful for marquee-style scrolling.
+
+    # Errors
+
+    Returns a `Result` that will report I2C errors, if any.
+    */
+    pub fn scroll_display_right(&mut self) -> Result<(), <I as i2c::Write>::Error> {
+        const LCD_CURSORSHIFT: u8 = 0x10;
+        const LCD_DISPLAYMOVE: u8 = 0x08;
+        const LCD_MOVERIGHT: u8 = 0x04;
+
+        self.command(LCD_CURSORSHIFT | LCD_DISPLAYMOVE | LCD_MOVERIGHT)
+    }
+
+    /**
+    Set the position of the cursor. `x` is the column and `y` is the row, both
+    0-indexed. Values beyond the display's geometry are clamped to the last valid
+    column/row.
 
     # Errors
 
     Returns a `Result` that will report I2C errors, if any.
     */
     pub fn set_cursor_position(&mut self, x: u8, y: u8) -> Result<(), <I as i2c::Write>::Error> {
-        let col = if y == 0_u8 { x | 0x80 } else { x | 0xC0 };
-        self.command(col)
+        const LCD_SETDDRAMADDR: u8 = 0x80;
+
+        let row = y.min(self.rows - 1);
+        let col = x.min(self.cols - 1);
+
+        self.cursor_row = row;
+        self.cursor_col = col;
+
+        self.command(LCD_SETDDRAMADDR | (self.row_offsets[row as usize] + col))
     }
 
     /**
@@ -180,6 +286,32 @@ where
         self.update_display_control()
     }
 
+    /**
+    Sets the direction text is entered in, and which way the cursor moves as
+    characters are written.
+
+    # Errors
+
+    Returns a `Result` that will report I2C errors, if any.
+    */
+    pub fn set_text_direction(&mut self, direction: TextDirection) -> Result<(), <I as i2c::Write>::Error> {
+        self.entry_mode.direction = direction;
+        self.update_entry_mode()
+    }
+
+    /**
+    Turns on/off shifting the whole display as each character is written, giving
+    the appearance of the cursor staying still while the text scrolls past it.
+
+    # Errors
+
+    Returns a `Result` that will report I2C errors, if any.
+    */
+    pub fn set_autoscroll(&mut self, autoscroll: Autoscroll) -> Result<(), <I as i2c::Write>::Error> {
+        self.entry_mode.autoscroll = autoscroll;
+        self.update_entry_mode()
+    }
+
     /**
     Adds a single character to the current position. The cursor will advance
     after this call to the next column
@@ -193,8 +325,9 @@ where
     }
 
     /**
-    Adds a string to the current position. The cursor will advance
-    after this call to the next column
+    Adds a string to the current position. The cursor will advance after each
+    character, wrapping to the start of the next row once it reaches the column
+    limit. A `'\n'` moves directly to the start of the next row.
 
     # Errors
 
@@ -202,31 +335,115 @@ where
     */
     pub fn write_str(&mut self, s: &str) -> Result<(), <I as i2c::Write>::Error> {
         for c in s.chars() {
+            if c == '\n' {
+                self.move_to_next_row()?;
+                continue;
+            }
+
+            if self.cursor_col >= self.cols {
+                self.move_to_next_row()?;
+            }
+
             self.write_char(c)?;
+            self.cursor_col += 1;
+        }
+
+        Ok(())
+    }
+
+    // Moves the cursor to the start of the next row, wrapping back to the first row
+    fn move_to_next_row(&mut self) -> Result<(), <I as i2c::Write>::Error> {
+        let next_row = (self.cursor_row + 1) % self.rows;
+        self.set_cursor_position(0, next_row)
+    }
+
+    /**
+    Takes the last I2C error recorded while writing through the `core::fmt::Write`
+    implementation, leaving `None` in its place.
+
+    `core::fmt::Write::write_str` can only return `fmt::Error`, so the I2C error that
+    triggered it is stashed here instead of being silently dropped.
+    */
+    pub fn take_error(&mut self) -> Option<<I as i2c::Write>::Error> {
+        self.last_error.take()
+    }
+
+    /**
+    Loads a custom character into one of the 8 CGRAM slots (`location` 0..=7, any
+    higher value is masked down into range). `bitmap` holds one byte per row of the
+    5x8 dot character, only the low 5 bits of each row are used.
+
+    The display's address pointer is left pointing at CGRAM after this call, so
+    callers should set a cursor position with `set_cursor_position()` before writing
+    to DDRAM again. Once loaded, the custom glyph can be shown with
+    `write_char(location as u8 as char)`.
+
+    # Errors
+
+    Returns a `Result` that will report I2C errors, if any.
+    */
+    pub fn create_char(&mut self, location: u8, bitmap: [u8; 8]) -> Result<(), <I as i2c::Write>::Error> {
+        const LCD_SETCGRAMADDR: u8 = 0x40;
+
+        let location = location & 0x07;
+        self.command(LCD_SETCGRAMADDR | (location << 3))?;
+
+        for row in bitmap.iter() {
+            self.write_two(0x40, *row)?;
         }
 
         Ok(())
     }
 
     /**
-    Set the color of the backlight for displays that have an RGB backlight.
+    Sets the software contrast (0..=63) on ST7032i-family controllers by selecting
+    the extended instruction set, issuing the contrast commands, then returning to
+    the normal instruction set. On `Controller::Aip31068` boards, which have no
+    extended instruction table, this is a no-op.
+
+    # Errors
+
+    Returns a `Result` that will report I2C errors, if any.
+    */
+    pub fn set_contrast(&mut self, level: u8) -> Result<(), <I as i2c::Write>::Error> {
+        if self.controller != Controller::St7032i {
+            return Ok(());
+        }
+
+        const LCD_FUNCTIONSET: u8 = 0x20;
+        const LCD_EXTENDEDINSTRUCTION: u8 = 0x01;
+        const LCD_CONTRASTSET: u8 = 0x70;
+        const LCD_POWERICONCONTRASTSET: u8 = 0x50;
+        const LCD_BOOSTERON: u8 = 0x08;
+
+        self.command(LCD_FUNCTIONSET | self.show_function | LCD_EXTENDEDINSTRUCTION)?;
+        self.command(LCD_CONTRASTSET | (level & 0x0F))?;
+        self.command(LCD_POWERICONCONTRASTSET | LCD_BOOSTERON | ((level >> 4) & 0x03))?;
+        self.command(LCD_FUNCTIONSET | self.show_function)
+    }
+
+    /**
+    Set the color of the backlight for displays that have an RGB backlight. Has no
+    effect on boards using `NoBacklight`; a non-RGB backlight handles the channels
+    as its `Backlight::set_color()` implementation does.
 
     # Errors
 
     Returns a `Result` that will report I2C errors, if any.
     */
     pub fn set_rgb(&mut self, r: u8, g: u8, b: u8) -> Result<(), <I as i2c::Write>::Error> {
-        const REG_RED: u8       = 0x04;        // pwm2
-        const REG_GREEN: u8     = 0x03;        // pwm1
-        const REG_BLUE: u8      = 0x02;        // pwm0
-    
-        self.set_reg(REG_RED, r)?;
-        self.set_reg(REG_GREEN, g)?;
-        self.set_reg(REG_BLUE, b)
+        self.backlight.set_color(&mut self.i2c, r, g, b)
     }
 
-    fn set_reg(&mut self, addr: u8, data: u8) -> Result<(), <I as i2c::Write>::Error> {
-        self.i2c.write(self.rgb_address, &[addr, data])
+    /**
+    Turns the backlight fully on or off.
+
+    # Errors
+
+    Returns a `Result` that will report I2C errors, if any.
+    */
+    pub fn set_backlight(&mut self, on: bool) -> Result<(), <I as i2c::Write>::Error> {
+        self.backlight.set_on(&mut self.i2c, on)
     }
 
     // Set one of the display's control options and then send the updated set of options to the display
@@ -234,6 +451,11 @@ where
         self.command(self.control.value())
     }
 
+    // Set one of the entry mode options and then send the updated entry mode to the display
+    fn update_entry_mode(&mut self) -> Result<(), <I as i2c::Write>::Error> {
+        self.command(self.entry_mode.value())
+    }
+
     // Send a command to the LCD display
     fn command(&mut self, value: u8) -> Result<(), <I as i2c::Write>::Error> {
         self.write_two(0x80, value)
@@ -244,3 +466,25 @@ where
         self.i2c.write(self.address, &[byte1, byte2])
     }
 }
+
+/**
+Allows the display to be used with the `write!` macro, e.g. `write!(lcd, "Vdd: {:.2}V", v)`.
+
+Since `fmt::Write::write_str` can only return `fmt::Error`, any I2C error encountered
+while writing is stashed and can be retrieved afterwards with `take_error()`.
+*/
+impl<I, B> fmt::Write for Lcd<I, B>
+where
+    I: i2c::Write,
+    B: Backlight<I>,
+{
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        match Lcd::write_str(self, s) {
+            Ok(()) => Ok(()),
+            Err(error) => {
+                self.last_error = Some(error);
+                Err(fmt::Error)
+            }
+        }
+    }
+}
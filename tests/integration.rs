@@ -1,7 +1,10 @@
+use core::fmt::Write as _;
 use embedded_hal_mock::common::Generic;
 use embedded_hal_mock::delay::MockNoop;
 use embedded_hal_mock::i2c::{Mock as I2cMock, Transaction as I2cTransaction};
-use lcd_1602_i2c::{Lcd, Cursor, LcdDisplay, Blink};
+use embedded_hal_mock::MockError;
+use lcd_1602_i2c::{Autoscroll, Blink, Controller, Cursor, Lcd, LcdDisplay, NoBacklight, Pca9633Backlight, TextDirection};
+use std::io::ErrorKind;
 use std::vec;
 
 const BLINK_ON: u8 = 0x01;
@@ -10,6 +13,9 @@ const DISPLAY_ON: u8 = 0x04;
 const DISPLAY_CONTROL: u8 = 0x08;
 const EXPECTED_ADDRESS: u8 = 123;
 const RGB_ADDRESS: u8 = 34;
+const ROWS: u8 = 2;
+const COLS: u8 = 16;
+const CONTROLLER: Controller = Controller::Aip31068;
 
 // Ensure the initialization sequence doesn't break
 #[test]
@@ -18,6 +24,68 @@ fn lcd_initialization() {
     let _ = new_lcd(&expectations, EXPECTED_ADDRESS, RGB_ADDRESS);
 }
 
+#[test]
+fn lcd_initialization_with_no_backlight_skips_rgb_register_writes() {
+    // Arrange
+    let expectations = Expectations::new()
+        // Initial command sequence for "wakeup"
+        .command_bytes(EXPECTED_ADDRESS, 0x28)
+        .command_bytes(EXPECTED_ADDRESS, 0x28)
+        .command_bytes(EXPECTED_ADDRESS, 0x28)
+        // Display On
+        .command_bytes(EXPECTED_ADDRESS, 8 | 4)
+        // Clear
+        .command_bytes(EXPECTED_ADDRESS, 0x01)
+        // Set LCD mode
+        .command_bytes(EXPECTED_ADDRESS, 0x02 | 0x04);
+
+    let mut delay = MockNoop::new();
+    let i2c = I2cMock::new(expectations.as_array());
+
+    // Act
+    let _ = Lcd::new(i2c, EXPECTED_ADDRESS, NoBacklight, CONTROLLER, ROWS, COLS, &mut delay).unwrap();
+}
+
+#[test]
+fn lcd_initialization_one_row_sends_1line_function_set() {
+    // Arrange
+    let expectations = Expectations::new()
+        // Initial command sequence for "wakeup", without the 2-line bit set
+        .command_bytes(EXPECTED_ADDRESS, 0x20)
+        .command_bytes(EXPECTED_ADDRESS, 0x20)
+        .command_bytes(EXPECTED_ADDRESS, 0x20)
+        // Display On
+        .command_bytes(EXPECTED_ADDRESS, 8 | 4)
+        // Clear
+        .command_bytes(EXPECTED_ADDRESS, 0x01)
+        // Set LCD mode
+        .command_bytes(EXPECTED_ADDRESS, 0x02 | 0x04);
+
+    let mut delay = MockNoop::new();
+    let i2c = I2cMock::new(expectations.as_array());
+
+    // Act
+    let _ = Lcd::new(i2c, EXPECTED_ADDRESS, NoBacklight, CONTROLLER, 1, COLS, &mut delay).unwrap();
+}
+
+#[test]
+#[should_panic(expected = "rows must be between 1 and 4")]
+fn new_panics_for_out_of_range_row_count() {
+    let mut delay = MockNoop::new();
+    let i2c = I2cMock::new(&[]);
+
+    let _ = Lcd::new(i2c, EXPECTED_ADDRESS, NoBacklight, CONTROLLER, 0, COLS, &mut delay);
+}
+
+#[test]
+#[should_panic(expected = "cols must be between 1 and 191")]
+fn new_panics_for_out_of_range_col_count() {
+    let mut delay = MockNoop::new();
+    let i2c = I2cMock::new(&[]);
+
+    let _ = Lcd::new(i2c, EXPECTED_ADDRESS, NoBacklight, CONTROLLER, ROWS, 200, &mut delay);
+}
+
 #[test]
 fn display_off() {
     // Arrange
@@ -87,14 +155,238 @@ fn blink_off() {
     let _ = lcd.set_blink(Blink::Off);
 }
 
+#[test]
+fn set_cursor_position_second_row() {
+    // Arrange
+    let expectations =
+        lcd_expectations(EXPECTED_ADDRESS, RGB_ADDRESS)
+        .command_bytes(EXPECTED_ADDRESS, 0x80 | 0x40 | 3);
+
+    let mut lcd = new_lcd(&expectations, EXPECTED_ADDRESS, RGB_ADDRESS);
+
+    // Act
+    let _ = lcd.set_cursor_position(3, 1);
+}
+
+#[test]
+fn set_cursor_position_clamps_out_of_range_row_and_col() {
+    // Arrange
+    let expectations =
+        lcd_expectations(EXPECTED_ADDRESS, RGB_ADDRESS)
+        .command_bytes(EXPECTED_ADDRESS, 0x80 | 0x40 | (COLS - 1));
+
+    let mut lcd = new_lcd(&expectations, EXPECTED_ADDRESS, RGB_ADDRESS);
+
+    // Act
+    let _ = lcd.set_cursor_position(255, 255);
+}
+
+#[test]
+fn write_str_wraps_to_next_row_at_column_limit() {
+    // Arrange
+    let mut expectations = lcd_expectations(EXPECTED_ADDRESS, RGB_ADDRESS);
+    for c in core::iter::repeat_n('a', COLS as usize) {
+        expectations = expectations.data_bytes(EXPECTED_ADDRESS, c as u8);
+    }
+    expectations = expectations
+        .command_bytes(EXPECTED_ADDRESS, 0x80 | 0x40)
+        .data_bytes(EXPECTED_ADDRESS, b'b');
+
+    let mut lcd = new_lcd(&expectations, EXPECTED_ADDRESS, RGB_ADDRESS);
+    let text: std::string::String = core::iter::repeat_n('a', COLS as usize).chain(core::iter::once('b')).collect();
+
+    // Act
+    let _ = lcd.write_str(&text);
+}
+
+#[test]
+fn write_str_newline_moves_to_start_of_next_row() {
+    // Arrange
+    let expectations =
+        lcd_expectations(EXPECTED_ADDRESS, RGB_ADDRESS)
+        .data_bytes(EXPECTED_ADDRESS, b'a')
+        .command_bytes(EXPECTED_ADDRESS, 0x80 | 0x40)
+        .data_bytes(EXPECTED_ADDRESS, b'b');
+
+    let mut lcd = new_lcd(&expectations, EXPECTED_ADDRESS, RGB_ADDRESS);
+
+    // Act
+    let _ = lcd.write_str("a\nb");
+}
+
+#[test]
+fn right_to_left() {
+    // Arrange
+    let expectations =
+        lcd_expectations(EXPECTED_ADDRESS, RGB_ADDRESS)
+        .command_bytes(EXPECTED_ADDRESS, 0x04);
+
+    let mut lcd = new_lcd(&expectations, EXPECTED_ADDRESS, RGB_ADDRESS);
+
+    // Act
+    let _ = lcd.set_text_direction(TextDirection::RightToLeft);
+}
+
+#[test]
+fn autoscroll_on() {
+    // Arrange
+    let expectations =
+        lcd_expectations(EXPECTED_ADDRESS, RGB_ADDRESS)
+        .command_bytes(EXPECTED_ADDRESS, 0x04 | 0x02 | 0x01);
+
+    let mut lcd = new_lcd(&expectations, EXPECTED_ADDRESS, RGB_ADDRESS);
+
+    // Act
+    let _ = lcd.set_autoscroll(Autoscroll::On);
+}
+
+#[test]
+fn return_home() {
+    // Arrange
+    let expectations =
+        lcd_expectations(EXPECTED_ADDRESS, RGB_ADDRESS)
+        .command_bytes(EXPECTED_ADDRESS, 0x02);
+
+    let mut lcd = new_lcd(&expectations, EXPECTED_ADDRESS, RGB_ADDRESS);
+    let mut delay = MockNoop::new();
+
+    // Act
+    let _ = lcd.return_home(&mut delay);
+}
+
+#[test]
+fn scroll_display_left() {
+    // Arrange
+    let expectations =
+        lcd_expectations(EXPECTED_ADDRESS, RGB_ADDRESS)
+        .command_bytes(EXPECTED_ADDRESS, 0x10 | 0x08);
+
+    let mut lcd = new_lcd(&expectations, EXPECTED_ADDRESS, RGB_ADDRESS);
+
+    // Act
+    let _ = lcd.scroll_display_left();
+}
+
+#[test]
+fn scroll_display_right() {
+    // Arrange
+    let expectations =
+        lcd_expectations(EXPECTED_ADDRESS, RGB_ADDRESS)
+        .command_bytes(EXPECTED_ADDRESS, 0x10 | 0x08 | 0x04);
+
+    let mut lcd = new_lcd(&expectations, EXPECTED_ADDRESS, RGB_ADDRESS);
+
+    // Act
+    let _ = lcd.scroll_display_right();
+}
+
+#[test]
+fn set_contrast_is_a_no_op_on_aip31068() {
+    // Arrange
+    let expectations = lcd_expectations(EXPECTED_ADDRESS, RGB_ADDRESS);
+    let mut lcd = new_lcd(&expectations, EXPECTED_ADDRESS, RGB_ADDRESS);
+
+    // Act
+    let _ = lcd.set_contrast(40);
+}
+
+#[test]
+fn set_contrast_on_st7032i() {
+    // Arrange
+    let expectations =
+        lcd_expectations(EXPECTED_ADDRESS, RGB_ADDRESS)
+        .command_bytes(EXPECTED_ADDRESS, 0x20 | 0x08 | 0x01)
+        .command_bytes(EXPECTED_ADDRESS, 0x70 | (40 & 0x0F))
+        .command_bytes(EXPECTED_ADDRESS, 0x50 | 0x08 | ((40 >> 4) & 0x03))
+        .command_bytes(EXPECTED_ADDRESS, 0x20 | 0x08);
+
+    let mut delay = MockNoop::new();
+    let i2c = I2cMock::new(expectations.as_array());
+    let backlight = Pca9633Backlight::new(RGB_ADDRESS);
+    let mut lcd = Lcd::new(i2c, EXPECTED_ADDRESS, backlight, Controller::St7032i, ROWS, COLS, &mut delay).unwrap();
+
+    // Act
+    let _ = lcd.set_contrast(40);
+}
+
+#[test]
+fn write_macro_drives_write_str() {
+    // Arrange
+    let expectations = lcd_expectations(EXPECTED_ADDRESS, RGB_ADDRESS)
+        .data_bytes(EXPECTED_ADDRESS, b'1')
+        .data_bytes(EXPECTED_ADDRESS, b'0');
+
+    let mut lcd = new_lcd(&expectations, EXPECTED_ADDRESS, RGB_ADDRESS);
+
+    // Act
+    let result = write!(lcd, "{}", 10);
+
+    // Assert
+    assert!(result.is_ok());
+}
+
+#[test]
+fn write_macro_stashes_i2c_error_and_returns_fmt_error() {
+    // Arrange
+    let error = MockError::Io(ErrorKind::Other);
+    let expectations =
+        lcd_expectations(EXPECTED_ADDRESS, RGB_ADDRESS)
+        .data_bytes_with_error(EXPECTED_ADDRESS, b'x', error.clone());
+
+    let mut lcd = new_lcd(&expectations, EXPECTED_ADDRESS, RGB_ADDRESS);
+
+    // Act
+    let result = write!(lcd, "x");
+
+    // Assert
+    assert_eq!(Err(core::fmt::Error), result);
+    assert_eq!(Some(error), lcd.take_error());
+    assert_eq!(None, lcd.take_error());
+}
+
+#[test]
+fn create_char() {
+    // Arrange
+    let bitmap = [0x0E, 0x11, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x00];
+    let mut expectations =
+        lcd_expectations(EXPECTED_ADDRESS, RGB_ADDRESS)
+        .command_bytes(EXPECTED_ADDRESS, 0x40 | (3 << 3));
+    for row in bitmap.iter() {
+        expectations = expectations.data_bytes(EXPECTED_ADDRESS, *row);
+    }
+
+    let mut lcd = new_lcd(&expectations, EXPECTED_ADDRESS, RGB_ADDRESS);
+
+    // Act
+    let _ = lcd.create_char(3, bitmap);
+}
+
+#[test]
+fn create_char_masks_location_to_three_bits() {
+    // Arrange
+    let bitmap = [0; 8];
+    let mut expectations =
+        lcd_expectations(EXPECTED_ADDRESS, RGB_ADDRESS)
+        .command_bytes(EXPECTED_ADDRESS, 0x40 | (1 << 3));
+    for row in bitmap.iter() {
+        expectations = expectations.data_bytes(EXPECTED_ADDRESS, *row);
+    }
+
+    let mut lcd = new_lcd(&expectations, EXPECTED_ADDRESS, RGB_ADDRESS);
+
+    // Act
+    let _ = lcd.create_char(9, bitmap);
+}
+
 fn new_lcd<'a>(
     expectations: &'a Expectations,
     address: u8,
     rgb_address: u8,
-) -> Lcd<Generic<I2cTransaction>> {
+) -> Lcd<Generic<I2cTransaction>, Pca9633Backlight> {
     let mut delay = MockNoop::new();
     let i2c = I2cMock::new(expectations.as_array());
-    Lcd::new(i2c, address, rgb_address, &mut delay).unwrap()
+    let backlight = Pca9633Backlight::new(rgb_address);
+    Lcd::new(i2c, address, backlight, CONTROLLER, ROWS, COLS, &mut delay).unwrap()
 }
 
 // Returns expectations for the initialization of the LCD display that is always
@@ -134,6 +426,18 @@ impl Expectations {
         self
     }
 
+    pub fn data_bytes(mut self, address: u8, byte: u8) -> Self {
+        self.expectations
+            .push(I2cTransaction::write(address, vec![0x40, byte]));
+        self
+    }
+
+    pub fn data_bytes_with_error(mut self, address: u8, byte: u8, error: MockError) -> Self {
+        self.expectations
+            .push(I2cTransaction::write(address, vec![0x40, byte]).with_error(error));
+        self
+    }
+
     fn reg_bytes(mut self, address: u8, reg: u8, byte: u8) -> Self {
         self.expectations
             .push(I2cTransaction::write(address, vec![reg, byte]));